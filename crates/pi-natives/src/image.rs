@@ -3,14 +3,70 @@
 //! Provides only the subset of functionality needed:
 //! - Load image from bytes (PNG, JPEG, WebP, GIF)
 //! - Get dimensions
-//! - Resize with Lanczos3 filter
-//! - Export as PNG, JPEG, WebP, or GIF
+//! - Resize exactly, fit within bounds, or generate center-cropped
+//!   thumbnails, all with a choice of sampling filter
+//! - Export as PNG, JPEG, WebP (lossless or lossy), GIF, TIFF, or AVIF
+//!   (behind the `avif` feature), either directly or through the unified
+//!   `encode` method
 
 use std::io::Cursor;
 
 use image::{imageops::FilterType, DynamicImage, ImageFormat, ImageReader};
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
+#[cfg(feature = "avif")]
+use libavif::{AvifData, Encoder as AvifEncoder, RgbPixels, YuvFormat};
+use tiff::encoder::{colortype, compression, TiffEncoder};
+use webp::{Encoder as WebPEncoder, PixelLayout};
+
+/// WebP encoding mode, mirroring libwebp's simple encoding API.
+#[derive(Clone, Copy)]
+pub enum WebpQuality {
+	/// Lossless encoding; ignores the quality factor entirely.
+	Lossless,
+	/// Lossy encoding at the given quality, clamped to 0.0 (smallest/worst)
+	/// through 100.0 (largest/best).
+	Lossy(f32),
+}
+
+/// Output container format for [`PhotonImage::encode`].
+#[napi]
+pub enum OutputFormat {
+	Png,
+	Jpeg,
+	WebP,
+	WebPLossless,
+	Gif,
+	Avif,
+	/// Pick a format automatically based on the image's own characteristics.
+	Auto,
+}
+
+/// Options for [`PhotonImage::encode`].
+///
+/// Unused fields are ignored for formats that don't need them (e.g. `speed`
+/// is only consulted for AVIF).
+#[napi(object)]
+#[derive(Debug, Default)]
+pub struct EncodeOptions {
+	/// Quality, 0-100. Defaults to 80 when a lossy format needs one.
+	pub quality:     Option<u32>,
+	/// Encoder speed, 0 (slowest/best) to 10 (fastest). Only used by AVIF.
+	pub speed:       Option<u32>,
+	/// For `OutputFormat::Auto`, prefer WebP (lossless or lossy) over PNG/JPEG
+	/// as the concrete container. Defaults to `false`.
+	pub prefer_webp: Option<bool>,
+}
+
+/// Compression algorithm for [`PhotonImage::get_bytes_tiff`], mapped onto the
+/// `tiff` crate's encoder compressors.
+#[napi]
+pub enum TiffCompression {
+	Uncompressed,
+	Lzw,
+	Deflate,
+	Packbits,
+}
 
 /// Sampling filter for resize operations.
 #[napi]
@@ -107,13 +163,24 @@ impl PhotonImage {
 	/// Returns an error if WebP encoding fails.
 	#[napi(js_name = "get_bytes_webp")]
 	pub fn get_bytes_webp(&self) -> Result<Uint8Array> {
-		let mut buffer = Vec::new();
-		let encoder = image::codecs::webp::WebPEncoder::new_lossless(&mut buffer);
-		self
-			.img
-			.write_with_encoder(encoder)
-			.map_err(|e| Error::from_reason(format!("Failed to encode WebP: {e}")))?;
-		Ok(Uint8Array::from(buffer))
+		self.encode_webp(WebpQuality::Lossless)
+	}
+
+	/// Export image as lossy WebP bytes at the given quality.
+	///
+	/// `quality` is clamped to the inclusive range 0.0-100.0; `NaN` is
+	/// rejected.
+	///
+	/// # Errors
+	/// Returns an error if `quality` is `NaN` or WebP encoding fails.
+	#[napi(js_name = "get_bytes_webp_lossy")]
+	pub fn get_bytes_webp_lossy(&self, quality: f64) -> Result<Uint8Array> {
+		let quality = quality as f32;
+		if quality.is_nan() {
+			return Err(Error::from_reason("WebP quality must not be NaN"));
+		}
+		let quality = quality.clamp(0.0, 100.0);
+		self.encode_webp(WebpQuality::Lossy(quality))
 	}
 
 	/// Export image as GIF bytes.
@@ -130,10 +197,202 @@ impl PhotonImage {
 		Ok(Uint8Array::from(buffer))
 	}
 
-	/// Resize the image to the specified dimensions.
+	/// Encode the image as `format`, using `options` for quality/speed where
+	/// applicable.
+	///
+	/// `OutputFormat::Auto` picks a lossless format (PNG, or lossless WebP
+	/// when `options.prefer_webp` is set) when the image has an alpha
+	/// channel or looks non-photographic (few distinct colors), and a lossy
+	/// format (JPEG, or lossy WebP when `options.prefer_webp` is set)
+	/// otherwise.
+	///
+	/// # Errors
+	/// Returns an error if encoding in the resolved format fails.
+	#[napi(js_name = "encode")]
+	pub fn encode(&self, format: OutputFormat, options: Option<EncodeOptions>) -> Result<Uint8Array> {
+		let options = options.unwrap_or_default();
+		let quality = options.quality.unwrap_or(80).min(100) as u8;
+		let prefer_webp = options.prefer_webp.unwrap_or(false);
+
+		match format {
+			OutputFormat::Png => self.get_bytes(),
+			OutputFormat::Jpeg => self.get_bytes_jpeg(quality),
+			OutputFormat::WebP => self.get_bytes_webp_lossy(f64::from(quality)),
+			OutputFormat::WebPLossless => self.get_bytes_webp(),
+			OutputFormat::Gif => self.get_bytes_gif(),
+			// `speed` is only meaningful to the AVIF encoder, so it's read here
+			// rather than hoisted into a shared local that would go unused
+			// when the `avif` feature is off.
+			#[cfg(feature = "avif")]
+			OutputFormat::Avif => self.get_bytes_avif(quality, options.speed.unwrap_or(6).min(10) as u8),
+			#[cfg(not(feature = "avif"))]
+			OutputFormat::Avif => Err(Error::from_reason("AVIF support was not compiled in (missing `avif` feature)")),
+			OutputFormat::Auto => match (self.prefers_lossless(), prefer_webp) {
+				(true, true) => self.get_bytes_webp(),
+				(true, false) => self.get_bytes(),
+				(false, true) => self.get_bytes_webp_lossy(f64::from(quality)),
+				(false, false) => self.get_bytes_jpeg(quality),
+			},
+		}
+	}
+
+	/// Resize the image to the specified dimensions, ignoring aspect ratio.
 	#[napi(js_name = "resize")]
 	pub fn resize(&self, width: u32, height: u32, filter: SamplingFilter) -> PhotonImage {
 		let resized = self.img.resize_exact(width, height, filter.into());
 		PhotonImage { img: resized }
 	}
+
+	/// Export image as TIFF bytes using the given compression.
+	///
+	/// # Errors
+	/// Returns an error if TIFF encoding fails.
+	#[napi(js_name = "get_bytes_tiff")]
+	pub fn get_bytes_tiff(&self, compression: TiffCompression) -> Result<Uint8Array> {
+		let buffer = match compression {
+			TiffCompression::Uncompressed => self.encode_tiff(compression::Uncompressed),
+			TiffCompression::Lzw => self.encode_tiff(compression::Lzw::default()),
+			TiffCompression::Deflate => self.encode_tiff(compression::Deflate::default()),
+			TiffCompression::Packbits => self.encode_tiff(compression::Packbits),
+		}?;
+		Ok(Uint8Array::from(buffer))
+	}
+
+	/// Resize to fit within `max_width` x `max_height`, preserving aspect
+	/// ratio. Only scales down; an image already within the bounding box is
+	/// returned unchanged.
+	#[napi(js_name = "resize_to_fit")]
+	pub fn resize_to_fit(&self, max_width: u32, max_height: u32, filter: SamplingFilter) -> PhotonImage {
+		let (width, height) = (self.img.width(), self.img.height());
+		let scale = (f64::from(max_width) / f64::from(width)).min(f64::from(max_height) / f64::from(height)).min(1.0);
+
+		let new_width = ((f64::from(width) * scale).round() as u32).max(1);
+		let new_height = ((f64::from(height) * scale).round() as u32).max(1);
+
+		let resized = self.img.resize_exact(new_width, new_height, filter.into());
+		PhotonImage { img: resized }
+	}
+
+	/// Resize to fill `width` x `height`, preserving aspect ratio, and
+	/// center-crop whatever overflows the target box.
+	#[napi(js_name = "thumbnail")]
+	pub fn thumbnail(&self, width: u32, height: u32, filter: SamplingFilter) -> PhotonImage {
+		let (src_width, src_height) = (self.img.width(), self.img.height());
+		let scale = (f64::from(width) / f64::from(src_width)).max(f64::from(height) / f64::from(src_height));
+
+		let scaled_width = ((f64::from(src_width) * scale).round() as u32).max(1);
+		let scaled_height = ((f64::from(src_height) * scale).round() as u32).max(1);
+
+		let scaled = self.img.resize_exact(scaled_width, scaled_height, filter.into());
+
+		let crop_x = scaled_width.saturating_sub(width) / 2;
+		let crop_y = scaled_height.saturating_sub(height) / 2;
+		let cropped = scaled.crop_imm(crop_x, crop_y, width.min(scaled_width), height.min(scaled_height));
+
+		PhotonImage { img: cropped }
+	}
+
+	/// Heuristic for `OutputFormat::Auto`: true when the image should be kept
+	/// lossless, i.e. it has transparency or looks non-photographic (a small
+	/// palette of distinct colors, as in screenshots, icons, and graphics).
+	fn prefers_lossless(&self) -> bool {
+		const DISTINCT_COLOR_LIMIT: usize = 4096;
+
+		if self.img.color().has_alpha() {
+			return true;
+		}
+
+		let rgb = self.img.to_rgb8();
+		let mut distinct = std::collections::HashSet::with_capacity(DISTINCT_COLOR_LIMIT + 1);
+		for pixel in rgb.pixels() {
+			distinct.insert(pixel.0);
+			if distinct.len() > DISTINCT_COLOR_LIMIT {
+				return false;
+			}
+		}
+		true
+	}
+
+	/// Encode `self.img` as TIFF using the given `tiff` crate compressor,
+	/// picking 8-bit gray/RGB/RGBA based on the image's own color type.
+	fn encode_tiff<C: tiff::encoder::compression::Compression>(&self, compression: C) -> Result<Vec<u8>> {
+		let (width, height) = (self.img.width(), self.img.height());
+		let mut buffer = Vec::new();
+		let mut encoder = TiffEncoder::new(Cursor::new(&mut buffer))
+			.map_err(|e| Error::from_reason(format!("Failed to create TIFF encoder: {e}")))?;
+
+		let result = if self.img.color().has_alpha() {
+			let rgba = self.img.to_rgba8();
+			encoder.write_image_with_compression::<colortype::RGBA8, C>(width, height, compression, &rgba)
+		} else if self.img.color() == image::ColorType::L8 {
+			let gray = self.img.to_luma8();
+			encoder.write_image_with_compression::<colortype::Gray8, C>(width, height, compression, &gray)
+		} else {
+			let rgb = self.img.to_rgb8();
+			encoder.write_image_with_compression::<colortype::RGB8, C>(width, height, compression, &rgb)
+		};
+
+		result.map_err(|e| Error::from_reason(format!("Failed to encode TIFF: {e}")))?;
+		Ok(buffer)
+	}
+
+	/// Encode `self.img` via `libwebp`, losslessly or at the given quality.
+	fn encode_webp(&self, quality: WebpQuality) -> Result<Uint8Array> {
+		let (width, height) = (self.img.width(), self.img.height());
+		let (pixels, layout) = if self.img.color().has_alpha() {
+			(self.img.to_rgba8().into_raw(), PixelLayout::Rgba)
+		} else {
+			(self.img.to_rgb8().into_raw(), PixelLayout::Rgb)
+		};
+
+		let encoder = WebPEncoder::new(&pixels, layout, width, height);
+		let memory = match quality {
+			WebpQuality::Lossless => encoder.encode_lossless(),
+			WebpQuality::Lossy(quality) => encoder.encode(quality),
+		};
+
+		if memory.is_empty() {
+			return Err(Error::from_reason("WebP encoding produced no output"));
+		}
+		Ok(Uint8Array::from(memory.to_vec()))
+	}
+}
+
+// Split into its own `#[napi] impl` block, gated on the `avif` feature: the
+// impl-level `#[napi]` macro wires up every method's N-API binding
+// unconditionally, so a method-level `#[cfg(feature = "avif")]` alone still
+// leaves a dangling reference to the binding when the feature is off.
+#[cfg(feature = "avif")]
+#[napi]
+impl PhotonImage {
+	/// Export image as AVIF bytes.
+	///
+	/// `quality` is 0-100 (mapped onto the encoder's quantizer scale) and
+	/// `speed` is 0 (slowest/best) through 10 (fastest).
+	///
+	/// # Errors
+	/// Returns an error if AVIF encoding fails.
+	#[napi(js_name = "get_bytes_avif")]
+	pub fn get_bytes_avif(&self, quality: u8, speed: u8) -> Result<Uint8Array> {
+		let (width, height) = (self.img.width(), self.img.height());
+		let pixels = if self.img.color().has_alpha() {
+			self.img.to_rgba8().into_raw()
+		} else {
+			self.img.to_rgb8().into_raw()
+		};
+
+		let image = RgbPixels::new(width, height, &pixels)
+			.map_err(|e| Error::from_reason(format!("Failed to build AVIF image: {e}")))?
+			.to_image(YuvFormat::Yuv444);
+
+		let mut encoder = AvifEncoder::new();
+		encoder.set_quality(quality);
+		encoder.set_speed(speed);
+
+		let data: AvifData = encoder
+			.encode(&image)
+			.map_err(|e| Error::from_reason(format!("Failed to encode AVIF: {e}")))?;
+
+		Ok(Uint8Array::from(data.as_ref().to_vec()))
+	}
 }